@@ -0,0 +1,108 @@
+use serde::Deserialize;
+use std::fs;
+
+const CONFIG_PATH: &str = "porter.toml";
+const DEFAULT_STAGE_DIR: &str = "./src/stage";
+
+// the letter keys `input::read_action` maps to actions, configurable so players
+// can remap to vi keys, WASD, or anything else without recompiling.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub left: char,
+    pub right: char,
+    pub up: char,
+    pub down: char,
+    pub reset: char,
+    pub undo: char,
+    pub redo: char,
+    pub hint: char,
+    pub quit: char,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            left: 'a',
+            right: 's',
+            up: 'w',
+            down: 'z',
+            reset: 'r',
+            undo: 'u',
+            redo: 'y',
+            hint: 'h',
+            quit: 'q',
+        }
+    }
+}
+
+// the glyph table `ui::render` draws each `Object` with.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Font {
+    pub space: String,
+    pub wall: String,
+    pub goal: String,
+    pub block: String,
+    pub block_on_goal: String,
+    pub man: String,
+    pub man_on_goal: String,
+}
+
+impl Font {
+    // order matches `Object`'s variant order: `ui::render` indexes into this by `as usize`.
+    pub fn glyphs(&self) -> [&str; 7] {
+        [
+            &self.space,
+            &self.wall,
+            &self.goal,
+            &self.block,
+            &self.block_on_goal,
+            &self.man,
+            &self.man_on_goal,
+        ]
+    }
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Font {
+            space: " ".to_string(),
+            wall: "#".to_string(),
+            goal: ".".to_string(),
+            block: "o".to_string(),
+            block_on_goal: "O".to_string(),
+            man: "p".to_string(),
+            man_on_goal: "P".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub keys: KeyBindings,
+    pub font: Font,
+    pub stage_dir: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            keys: KeyBindings::default(),
+            font: Font::default(),
+            stage_dir: DEFAULT_STAGE_DIR.to_string(),
+        }
+    }
+}
+
+impl Config {
+    // loads `porter.toml` from the current directory, falling back to the
+    // built-in defaults when it's absent or fails to parse.
+    pub fn load() -> Config {
+        fs::read_to_string(CONFIG_PATH)
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+}
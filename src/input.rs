@@ -0,0 +1,61 @@
+use crate::config::KeyBindings;
+use crossterm::event::{self, Event, KeyCode, KeyEvent};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use std::io;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+// the semantic action a keypress resolves to, independent of which physical
+// key (a configured letter or an arrow key) produced it.
+#[derive(Copy, Clone, Debug)]
+pub enum Action {
+    Move(i32, i32),
+    Undo,
+    Redo,
+    Hint,
+    Reset,
+    Quit,
+}
+
+// puts the terminal in raw mode for the duration of `f` and always restores
+// it afterward, even if `f` panics, so a crash never leaves the user's shell
+// broken.
+pub fn with_raw_mode<T>(f: impl FnOnce() -> T) -> io::Result<T> {
+    enable_raw_mode()?;
+    let result = catch_unwind(AssertUnwindSafe(f));
+    disable_raw_mode()?;
+    match result {
+        Ok(value) => Ok(value),
+        Err(payload) => resume_unwind(payload),
+    }
+}
+
+// blocks until a single recognized keypress arrives and returns the action
+// it maps to; unrecognized keys are swallowed and the next key is awaited.
+// the arrow keys always move regardless of `keys`, in addition to whatever
+// letters `keys` configures.
+pub fn read_action(keys: &KeyBindings) -> io::Result<Action> {
+    loop {
+        if let Event::Key(KeyEvent { code, .. }) = event::read()? {
+            let action = match code {
+                KeyCode::Left => Some(Action::Move(-1, 0)),
+                KeyCode::Right => Some(Action::Move(1, 0)),
+                KeyCode::Up => Some(Action::Move(0, -1)),
+                KeyCode::Down => Some(Action::Move(0, 1)),
+                KeyCode::Esc => Some(Action::Quit),
+                KeyCode::Char(c) if c == keys.left => Some(Action::Move(-1, 0)),
+                KeyCode::Char(c) if c == keys.right => Some(Action::Move(1, 0)),
+                KeyCode::Char(c) if c == keys.up => Some(Action::Move(0, -1)),
+                KeyCode::Char(c) if c == keys.down => Some(Action::Move(0, 1)),
+                KeyCode::Char(c) if c == keys.undo => Some(Action::Undo),
+                KeyCode::Char(c) if c == keys.redo => Some(Action::Redo),
+                KeyCode::Char(c) if c == keys.hint => Some(Action::Hint),
+                KeyCode::Char(c) if c == keys.reset => Some(Action::Reset),
+                KeyCode::Char(c) if c == keys.quit => Some(Action::Quit),
+                _ => None,
+            };
+            if let Some(action) = action {
+                return Ok(action);
+            }
+        }
+    }
+}
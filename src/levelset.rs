@@ -0,0 +1,131 @@
+use crate::Stage;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// a single XSB-format file may bundle several puzzles, each separated from
+// the next by a blank line and optionally preceded by a `Title:` line or a
+// `;`-prefixed comment naming it.
+pub struct LevelSet {
+    pub stages: Vec<Stage>,
+}
+
+impl LevelSet {
+    pub fn load(filepath: &str) -> LevelSet {
+        let contents = read_file(filepath);
+        let default_name = Path::new(filepath)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or(filepath)
+            .to_string();
+
+        LevelSet {
+            stages: parse(&contents, &default_name),
+        }
+    }
+}
+
+fn read_file(filepath: &str) -> String {
+    let mut f = File::open(filepath).expect("file not found");
+    let mut contents = String::new();
+    f.read_to_string(&mut contents)
+        .expect("failed to read file contents");
+    contents
+}
+
+// splits the file on blank lines into puzzles, pulling a name off a leading
+// `Title:`/`;`-comment line when present, else falling back to the set's
+// default name with a 1-based index.
+fn parse(contents: &str, default_name: &str) -> Vec<Stage> {
+    let mut stages = Vec::new();
+    let mut title: Option<String> = None;
+    let mut board = String::new();
+
+    for line in contents.lines() {
+        if board.is_empty() {
+            if let Some(text) = title_from_comment(line) {
+                title = Some(text);
+                continue;
+            }
+        } else if title_from_comment(line).is_some() {
+            // a trailing comment after the grid (e.g. a move/push count),
+            // not the puzzle's title; drop it without touching `title`.
+            continue;
+        }
+        if line.trim().is_empty() {
+            flush(&mut board, &mut title, &mut stages, default_name);
+            continue;
+        }
+        board.push_str(line);
+        board.push('\n');
+    }
+    flush(&mut board, &mut title, &mut stages, default_name);
+
+    stages
+}
+
+// pushes the puzzle accumulated in `board` as a `Stage`, naming it from
+// `title` if the source gave one, else a numbered fallback; a no-op on an
+// empty run, e.g. consecutive separator lines.
+fn flush(board: &mut String, title: &mut Option<String>, stages: &mut Vec<Stage>, default_name: &str) {
+    if board.is_empty() {
+        return;
+    }
+    let name = title
+        .take()
+        .unwrap_or_else(|| format!("{} #{}", default_name, stages.len() + 1));
+    stages.push(Stage::new(std::mem::take(board), name));
+}
+
+// recognizes the two common ways an XSB puzzle names itself: a `Title:` line
+// or a `;`-prefixed comment.
+fn title_from_comment(line: &str) -> Option<String> {
+    let trimmed = line.trim();
+    if let Some(rest) = trimmed.strip_prefix("Title:") {
+        return Some(rest.trim().to_string());
+    }
+    if let Some(rest) = trimmed.strip_prefix(';') {
+        let rest = rest.trim();
+        if !rest.is_empty() {
+            return Some(rest.to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_multiple_puzzles_on_blank_lines() {
+        let contents = "Title: First\n#####\n#p .#\n#####\n\nTitle: Second\n#####\n#p o#\n#####\n";
+        let stages = parse(contents, "pack");
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0].name, "First");
+        assert_eq!(stages[1].name, "Second");
+    }
+
+    #[test]
+    fn flushes_the_last_puzzle_without_a_trailing_blank_line() {
+        let contents = "#####\n#p .#\n#####";
+        let stages = parse(contents, "pack");
+        assert_eq!(stages.len(), 1);
+        assert_eq!(stages[0].name, "pack #1");
+    }
+
+    #[test]
+    fn a_trailing_comment_does_not_overwrite_the_leading_title() {
+        let contents = "Title: Real Name\n#####\n#p .#\n#####\n; 3 moves, 1 push\n\n";
+        let stages = parse(contents, "pack");
+        assert_eq!(stages.len(), 1);
+        assert_eq!(stages[0].name, "Real Name");
+    }
+
+    #[test]
+    fn alternate_floor_glyph_rows_are_not_mistaken_for_a_blank_separator() {
+        let contents = "#####\n#p_.#\n#####\n\n#####\n#p o#\n#####\n";
+        let stages = parse(contents, "pack");
+        assert_eq!(stages.len(), 2);
+    }
+}
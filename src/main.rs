@@ -1,12 +1,22 @@
-#![feature(rust_2018_preview)]
-
-use std::collections::HashMap;
-use std::fs::{read_dir, File};
-use std::io::Read;
-use std::io::{stdout, BufWriter, Write};
-
-const STAGEWIDTH: usize = 10;
-const STAGEHEIGHT: usize = 8;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs::read_dir;
+use std::io::Stdout;
+use std::thread;
+use std::time::Duration;
+
+use crossterm::execute;
+use crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::Terminal;
+
+mod config;
+mod input;
+mod levelset;
+mod ui;
+
+// the terminal type threaded through `Stage` wherever it needs to redraw.
+type Term = Terminal<CrosstermBackend<Stdout>>;
 
 #[derive(Copy, Clone, Debug)]
 enum Object {
@@ -17,20 +27,70 @@ enum Object {
     ObjBlockOnGoal,
     ObjMan,
     ObjManOnGoal,
+}
+
+// a single move's effect, recorded so it can be replayed backward (undo)
+// or forward (redo) without re-deriving it from the input that caused it.
+struct MoveDelta {
+    idx: Vec<usize>,
+    before: Vec<Object>,
+    after: Vec<Object>,
+}
+
+// (man position, sorted block positions). goals are fixed terrain and need
+// not be part of the key.
+type SolverState = (usize, Vec<usize>);
+
+// a node on the A* open list, ordered so `BinaryHeap` (a max-heap) pops the
+// lowest f-score first.
+struct SolverNode {
+    f: i32,
+    g: i32,
+    state: SolverState,
+}
 
-    ObjUnknown,
+impl Ord for SolverNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.f.cmp(&self.f)
+    }
+}
+
+impl PartialOrd for SolverNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SolverNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.f == other.f
+    }
 }
 
+impl Eq for SolverNode {}
+
 struct Stage {
     origin: String,
-    objects: [Object; STAGEWIDTH * STAGEHEIGHT],
+    name: String,
+    width: usize,
+    height: usize,
+    objects: Vec<Object>,
+    undo_stack: Vec<MoveDelta>,
+    redo_stack: Vec<MoveDelta>,
+    move_count: usize,
 }
 
 impl Stage {
-    fn initialize(filepath: &str) -> Stage {
+    fn new(origin: String, name: String) -> Stage {
         Stage {
-            origin: read_stage_file(filepath),
-            objects: [Object::ObjUnknown; STAGEWIDTH * STAGEHEIGHT],
+            origin,
+            name,
+            width: 0,
+            height: 0,
+            objects: Vec::new(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            move_count: 0,
         }
     }
 
@@ -43,72 +103,84 @@ impl Stage {
         object_map.insert('O', Object::ObjBlockOnGoal);
         object_map.insert('p', Object::ObjMan);
         object_map.insert('P', Object::ObjManOnGoal);
+        // the de-facto XSB/.sok symbols used by community level packs,
+        // recognized alongside porter's own glyphs above.
+        object_map.insert('$', Object::ObjBlock);
+        object_map.insert('*', Object::ObjBlockOnGoal);
+        object_map.insert('@', Object::ObjMan);
+        object_map.insert('+', Object::ObjManOnGoal);
+        // XSB packs also use `_`/`-` as alternate floor tiles, specifically
+        // so an all-floor row can't be mistaken for the blank line that
+        // separates puzzles in a multi-puzzle file.
+        object_map.insert('_', Object::ObjSpace);
+        object_map.insert('-', Object::ObjSpace);
+
+        // derive the grid from the stage text itself, so levels of any size load correctly.
+        self.width = self.origin.lines().map(|line| line.len()).max().unwrap_or(0);
+        self.height = self.origin.lines().count();
+        self.objects = vec![Object::ObjSpace; self.width * self.height];
 
         for (y, line) in self.origin.lines().enumerate() {
             for (x, data) in line.chars().enumerate() {
-                self.objects[y * STAGEWIDTH + x] = object_map[&data];
+                // an unrecognized glyph degrades to floor rather than
+                // panicking, so one odd character in a community level
+                // doesn't take down the whole program.
+                let object = object_map.get(&data).copied().unwrap_or(Object::ObjSpace);
+                self.objects[y * self.width + x] = object;
             }
         }
-    }
-
-    fn draw(&mut self) {
-        // draw stage using buffer for large data set.
-        let out = stdout();
-        let mut out = BufWriter::new(out.lock());
-
-        // clear the entire screen.
-        write!(out, "{}[2J", 27 as char).unwrap();
 
-        // order of the elements in the dict is same as enum Object.
-        let font = [" ", "#", ".", "o", "O", "p", "P"];
-
-        for y in 0..STAGEHEIGHT {
-            for x in 0..STAGEWIDTH {
-                write!(out, "{}", font[self.objects[y * STAGEWIDTH + x] as usize]).unwrap();
-            }
-            writeln!(out, "").unwrap();
-        }
+        // a (re)load starts the stage fresh, so any undo/redo history and
+        // move count from a previous attempt no longer applies.
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.move_count = 0;
     }
 
     fn action(&mut self, x: i32, dx: i32, y: i32, dy: i32) {
+        let width = self.width as i32;
+        let height = self.height as i32;
+
         // check whether 1 space forward from current position is under valid range.
         let tx = x + dx;
         let ty = y + dy;
-        if tx < 0 || ty < 0 || tx >= (STAGEWIDTH as i32) || ty >= (STAGEHEIGHT as i32) {
+        if tx < 0 || ty < 0 || tx >= width || ty >= height {
             return;
         }
 
         // position of person.
-        let p = (y * (STAGEWIDTH as i32) + x) as usize;
+        let p = (y * width + x) as usize;
         // target position to move forward.
-        let tp = (ty * (STAGEWIDTH as i32) + tx) as usize;
+        let tp = (ty * width + tx) as usize;
 
         match self.objects[tp] {
             Object::ObjSpace | Object::ObjGoal => {
+                let touched = [p, tp];
+                let before = Stage::snapshot(self, &touched);
                 Stage::update_goal_for_man(self, tp);
                 Stage::update_man_on_goal(self, p);
+                Stage::push_undo(self, &touched, before);
             }
             Object::ObjBlock | Object::ObjBlockOnGoal => {
                 // check whether 2 spaces forward from current position is under the valid range.
-                let tx2 = (tp as i32) + dx;
-                let ty2 = (tp as i32) + dy;
-                if tx2 < 0
-                    || ty2 < 0
-                    || tx2 >= ((STAGEWIDTH * STAGEHEIGHT) as i32)
-                    || ty2 >= ((STAGEHEIGHT * STAGEWIDTH) as i32)
-                {
+                let tx2 = tx + dx;
+                let ty2 = ty + dy;
+                if tx2 < 0 || ty2 < 0 || tx2 >= width || ty2 >= height {
                     return;
                 }
 
                 // 2 spaces forward from current position.
-                let tp2 = ((ty + dy) * (STAGEWIDTH as i32) + (tx + dx)) as usize;
+                let tp2 = (ty2 * width + tx2) as usize;
 
                 // check the object on current position, target position and 1 space forward from target position.
                 match self.objects[tp2] {
                     Object::ObjSpace | Object::ObjGoal => {
+                        let touched = [p, tp, tp2];
+                        let before = Stage::snapshot(self, &touched);
                         Stage::update_goal_for_block(self, tp2);
                         Stage::update_block_on_goal(self, tp);
                         Stage::update_man_on_goal(self, p);
+                        Stage::push_undo(self, &touched, before);
                     }
                     _ => {}
                 }
@@ -117,6 +189,42 @@ impl Stage {
         }
     }
 
+    fn snapshot(&self, idx: &[usize]) -> Vec<Object> {
+        idx.iter().map(|&i| self.objects[i]).collect()
+    }
+
+    // record a successful move so it can be undone, and drop the now-stale redo history.
+    fn push_undo(&mut self, idx: &[usize], before: Vec<Object>) {
+        let after = Stage::snapshot(self, idx);
+        self.undo_stack.push(MoveDelta {
+            idx: idx.to_vec(),
+            before,
+            after,
+        });
+        self.redo_stack.clear();
+        self.move_count += 1;
+    }
+
+    fn undo(&mut self) {
+        if let Some(delta) = self.undo_stack.pop() {
+            for (i, &idx) in delta.idx.iter().enumerate() {
+                self.objects[idx] = delta.before[i];
+            }
+            self.redo_stack.push(delta);
+            self.move_count -= 1;
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(delta) = self.redo_stack.pop() {
+            for (i, &idx) in delta.idx.iter().enumerate() {
+                self.objects[idx] = delta.after[i];
+            }
+            self.undo_stack.push(delta);
+            self.move_count += 1;
+        }
+    }
+
     fn update_goal_for_man(&mut self, idx: usize) {
         if let Object::ObjGoal = self.objects[idx] {
             self.objects[idx] = Object::ObjManOnGoal;
@@ -149,36 +257,191 @@ impl Stage {
         self.objects[idx] = Object::ObjSpace;
     }
 
-    fn update(&mut self, input: char) {
-        let mut dx = 0;
-        let mut dy = 0;
-        match input {
-            'a' => dx = -1,
-            's' => dx = 1,
-            'w' => dy = -1,
-            'z' => dy = 1,
-            'r' => {
-                Stage::reset(self);
-                return;
-            }
-            _ => println!("Input error: invalid input."),
-        }
-        let mut idx: usize = 0;
-        for (i, object) in self.objects.iter().enumerate() {
-            if let Object::ObjMan = *object {
-                idx = i;
-                break;
+    // dispatches a resolved input action; the key -> action mapping itself
+    // lives in `input`, driven by the player's `config::KeyBindings`.
+    fn apply(&mut self, action: input::Action, font: &config::Font, terminal: &mut Term) {
+        match action {
+            input::Action::Move(dx, dy) => {
+                let mut idx: usize = 0;
+                for (i, object) in self.objects.iter().enumerate() {
+                    if let Object::ObjMan = *object {
+                        idx = i;
+                        break;
+                    }
+                    if let Object::ObjManOnGoal = *object {
+                        idx = i;
+                        break;
+                    }
+                }
+
+                let x = (idx % self.width) as i32;
+                let y = (idx / self.width) as i32;
+
+                Stage::action(self, x, dx, y, dy);
             }
-            if let Object::ObjManOnGoal = *object {
-                idx = i;
-                break;
+            input::Action::Reset => Stage::reset(self, font, terminal),
+            input::Action::Undo => Stage::undo(self),
+            input::Action::Redo => Stage::redo(self),
+            input::Action::Hint => {
+                if let Some(moves) = Stage::solve(self) {
+                    for mv in moves {
+                        let (dx, dy) = match mv {
+                            'a' => (-1, 0),
+                            's' => (1, 0),
+                            'w' => (0, -1),
+                            'z' => (0, 1),
+                            _ => (0, 0),
+                        };
+                        Stage::apply(self, input::Action::Move(dx, dy), font, terminal);
+                        render(terminal, self, font);
+                        thread::sleep(Duration::from_millis(150));
+                    }
+                }
             }
+            // quitting the level is main's loop to make, not Stage's.
+            input::Action::Quit => {}
         }
+    }
+
+    // breadth-first / A* search over (man position, block positions) states
+    // for a sequence of moves that clears the stage. Returns the solution as
+    // `a/s/w/z` characters, which the `Hint` arm of `apply` turns into `Move` deltas.
+    fn solve(&self) -> Option<Vec<char>> {
+        let goals: HashSet<usize> = self
+            .objects
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| {
+                matches!(
+                    o,
+                    Object::ObjGoal | Object::ObjManOnGoal | Object::ObjBlockOnGoal
+                )
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let walls: HashSet<usize> = self
+            .objects
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| matches!(o, Object::ObjWall))
+            .map(|(i, _)| i)
+            .collect();
+
+        let man_start = self
+            .objects
+            .iter()
+            .position(|o| matches!(o, Object::ObjMan | Object::ObjManOnGoal))?;
+        let mut blocks_start: Vec<usize> = self
+            .objects
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| matches!(o, Object::ObjBlock | Object::ObjBlockOnGoal))
+            .map(|(i, _)| i)
+            .collect();
+        blocks_start.sort();
+
+        let width = self.width;
+        let height = self.height;
+        let heuristic = |blocks: &[usize]| -> i32 {
+            blocks
+                .iter()
+                .map(|&b| {
+                    goals
+                        .iter()
+                        .map(|&g| manhattan(b, g, width))
+                        .min()
+                        .unwrap_or(0)
+                })
+                .sum()
+        };
+
+        const DIRS: [(char, i32, i32); 4] = [('a', -1, 0), ('s', 1, 0), ('w', 0, -1), ('z', 0, 1)];
+
+        let start: SolverState = (man_start, blocks_start);
+        let mut open = BinaryHeap::new();
+        let mut g_score: HashMap<SolverState, i32> = HashMap::new();
+        let mut came_from: HashMap<SolverState, (SolverState, char)> = HashMap::new();
+        let mut visited: HashSet<SolverState> = HashSet::new();
+
+        g_score.insert(start.clone(), 0);
+        open.push(SolverNode {
+            f: heuristic(&start.1),
+            g: 0,
+            state: start,
+        });
+
+        while let Some(SolverNode { g, state, .. }) = open.pop() {
+            if visited.contains(&state) {
+                continue;
+            }
+            if state.1.iter().all(|b| goals.contains(b)) {
+                let mut path = Vec::new();
+                let mut cur = state;
+                while let Some((prev, mv)) = came_from.get(&cur) {
+                    path.push(*mv);
+                    cur = prev.clone();
+                }
+                path.reverse();
+                return Some(path);
+            }
+            visited.insert(state.clone());
+
+            let man = state.0;
+            let blocks = &state.1;
+            let x = (man % width) as i32;
+            let y = (man / width) as i32;
+
+            for &(mv, dx, dy) in DIRS.iter() {
+                let tx = x + dx;
+                let ty = y + dy;
+                if tx < 0 || ty < 0 || tx >= width as i32 || ty >= height as i32 {
+                    continue;
+                }
+                let tp = (ty as usize) * width + (tx as usize);
+                if walls.contains(&tp) {
+                    continue;
+                }
 
-        let x = (idx % STAGEWIDTH) as i32;
-        let y = (idx / STAGEWIDTH) as i32;
+                let mut new_blocks = blocks.clone();
+                if let Some(bi) = new_blocks.iter().position(|&b| b == tp) {
+                    let tx2 = tx + dx;
+                    let ty2 = ty + dy;
+                    if tx2 < 0 || ty2 < 0 || tx2 >= width as i32 || ty2 >= height as i32 {
+                        continue;
+                    }
+                    let tp2 = (ty2 as usize) * width + (tx2 as usize);
+                    if walls.contains(&tp2) || new_blocks.contains(&tp2) {
+                        continue;
+                    }
+                    if !goals.contains(&tp2) && is_deadlocked(tp2, width, height, &walls, &goals) {
+                        continue;
+                    }
+                    new_blocks[bi] = tp2;
+                    new_blocks.sort();
+                }
+
+                let new_state: SolverState = (tp, new_blocks);
+                if visited.contains(&new_state) {
+                    continue;
+                }
+                let tentative_g = g + 1;
+                let better = match g_score.get(&new_state) {
+                    Some(&existing) => tentative_g < existing,
+                    None => true,
+                };
+                if better {
+                    g_score.insert(new_state.clone(), tentative_g);
+                    came_from.insert(new_state.clone(), (state.clone(), mv));
+                    open.push(SolverNode {
+                        f: tentative_g + heuristic(&new_state.1),
+                        g: tentative_g,
+                        state: new_state,
+                    });
+                }
+            }
+        }
 
-        Stage::action(self, x, dx, y, dy);
+        None
     }
 
     fn check_clear(&self) -> bool {
@@ -190,41 +453,204 @@ impl Stage {
         return true;
     }
 
-    fn reset(&mut self) {
+    fn reset(&mut self, font: &config::Font, terminal: &mut Term) {
         Stage::load(self);
-        Stage::draw(self);
+        render(terminal, self, font);
     }
 }
 
-fn read_stage_file(filepath: &str) -> String {
-    let mut f = File::open(filepath).expect("file not found");
-    let mut stage_data: String = String::new();
-    f.read_to_string(&mut stage_data)
-        .expect("failed to read file contents");
-    return stage_data;
+// draws one frame of the current stage on the given terminal via `ui::render`.
+fn render(terminal: &mut Term, stage: &Stage, font: &config::Font) {
+    terminal
+        .draw(|frame| ui::render(frame, stage, font))
+        .expect("failed to draw frame");
 }
 
-fn main() {
-    let stage_files = read_dir("./src/stage").expect("directory not found");
-    for file in stage_files {
-        let mut state: Stage =
-            Stage::initialize(file.expect("file not found").path().to_str().unwrap());
+fn manhattan(a: usize, b: usize, width: usize) -> i32 {
+    let ax = (a % width) as i32;
+    let ay = (a / width) as i32;
+    let bx = (b % width) as i32;
+    let by = (b / width) as i32;
+    (ax - bx).abs() + (ay - by).abs()
+}
 
-        Stage::load(&mut state);
+// a block that is not on a goal can never be solved from if it's wedged into
+// a dead corner (walls on two perpendicular sides), or pinned against a wall
+// with no goal anywhere in the corridor it's confined to. Pruning these
+// successor states is what keeps the search tractable.
+fn is_deadlocked(
+    pos: usize,
+    width: usize,
+    height: usize,
+    walls: &HashSet<usize>,
+    goals: &HashSet<usize>,
+) -> bool {
+    let x = (pos % width) as i32;
+    let y = (pos / width) as i32;
+
+    let idx = |x: i32, y: i32| -> Option<usize> {
+        if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+            None
+        } else {
+            Some((y as usize) * width + (x as usize))
+        }
+    };
+    // off the board is equivalent to a wall for this check.
+    let is_wall = |x: i32, y: i32| -> bool {
+        match idx(x, y) {
+            Some(i) => walls.contains(&i),
+            None => true,
+        }
+    };
 
-        loop {
-            Stage::draw(&mut state);
+    let corners = [(-1, 0, 0, -1), (1, 0, 0, -1), (-1, 0, 0, 1), (1, 0, 0, 1)];
+    for &(hx, hy, vx, vy) in corners.iter() {
+        if is_wall(x + hx, y + hy) && is_wall(x + vx, y + vy) {
+            return true;
+        }
+    }
 
-            if Stage::check_clear(&state) {
-                println!("STAGE CLEAR!");
+    // the block is frozen along the line it's confined to if that wall holds
+    // for its whole length and no goal lies anywhere in the confined corridor.
+    let along_wall = |step: (i32, i32), wall_side: (i32, i32)| -> bool {
+        if !is_wall(x + wall_side.0, y + wall_side.1) {
+            return false;
+        }
+        let mut cx = x;
+        let mut cy = y;
+        loop {
+            if goals.contains(&((cy as usize) * width + (cx as usize))) {
+                return false;
+            }
+            if !is_wall(cx + wall_side.0, cy + wall_side.1) {
+                return false;
+            }
+            let (nx, ny) = (cx + step.0, cy + step.1);
+            if is_wall(nx, ny) {
                 break;
             }
+            cx = nx;
+            cy = ny;
+        }
+        let mut cx = x;
+        let mut cy = y;
+        loop {
+            let (nx, ny) = (cx - step.0, cy - step.1);
+            if is_wall(nx, ny) {
+                break;
+            }
+            cx = nx;
+            cy = ny;
+            if goals.contains(&((cy as usize) * width + (cx as usize))) {
+                return false;
+            }
+            if !is_wall(cx + wall_side.0, cy + wall_side.1) {
+                return false;
+            }
+        }
+        true
+    };
+
+    if along_wall((1, 0), (0, -1)) || along_wall((1, 0), (0, 1)) {
+        return true;
+    }
+    if along_wall((0, 1), (-1, 0)) || along_wall((0, 1), (1, 0)) {
+        return true;
+    }
 
-            println!("a: left s: right w: up z: down r: reset. Input command?");
+    false
+}
+
+// leaves the alternate screen on drop, including when the closure that holds
+// it unwinds from a panic, so a crash never strands the user's shell on the
+// alternate buffer the way a plain `execute!(..., LeaveAlternateScreen)` at
+// the end of `main`'s closure would.
+struct AltScreen;
+
+impl AltScreen {
+    fn enter(out: &mut Stdout) -> AltScreen {
+        execute!(out, EnterAlternateScreen).expect("failed to enter alternate screen");
+        AltScreen
+    }
+}
+
+impl Drop for AltScreen {
+    fn drop(&mut self) {
+        execute!(std::io::stdout(), LeaveAlternateScreen).ok();
+    }
+}
+
+fn main() {
+    let config = config::Config::load();
+
+    let stage_files = read_dir(&config.stage_dir).expect("directory not found");
+
+    input::with_raw_mode(|| {
+        let mut out = std::io::stdout();
+        let _alt_screen = AltScreen::enter(&mut out);
+        let mut terminal =
+            Terminal::new(CrosstermBackend::new(out)).expect("failed to start terminal");
+
+        for file in stage_files {
+            let path = file.expect("file not found").path();
+            let level_set = levelset::LevelSet::load(path.to_str().unwrap());
+
+            for mut state in level_set.stages {
+                Stage::load(&mut state);
+
+                loop {
+                    render(&mut terminal, &state, &config.font);
 
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input).ok();
-            Stage::update(&mut state, input.chars().nth(0).unwrap());
+                    if Stage::check_clear(&state) {
+                        // wait for a keypress before moving on to the next stage.
+                        input::read_action(&config.keys).ok();
+                        break;
+                    }
+
+                    match input::read_action(&config.keys).expect("failed to read input") {
+                        input::Action::Quit => break,
+                        action => Stage::apply(&mut state, action, &config.font, &mut terminal),
+                    }
+                }
+            }
         }
+    })
+    .expect("failed to manage raw terminal mode");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stage_from(origin: &str) -> Stage {
+        let mut stage = Stage::new(origin.to_string(), "test".to_string());
+        stage.load();
+        stage
+    }
+
+    #[test]
+    fn solves_a_trivial_push() {
+        let stage = stage_from("######\n#p o.#\n######\n");
+        assert_eq!(stage.solve(), Some(vec!['s', 's']));
+    }
+
+    #[test]
+    fn block_against_a_wall_with_a_goal_further_along_is_not_frozen() {
+        // wall runs along the whole row above the block; a goal further
+        // along the row the block is confined to means it's still solvable.
+        let width = 5;
+        let height = 3;
+        let walls: HashSet<usize> = (0..width).collect();
+        let goals: HashSet<usize> = [width + 3].iter().copied().collect();
+        let pos = width + 1;
+        assert!(!is_deadlocked(pos, width, height, &walls, &goals));
+    }
+
+    #[test]
+    fn unsolvable_board_returns_none() {
+        // the block starts wedged into a literal corner (walls both above
+        // and to its left), so no push can ever free it.
+        let stage = stage_from("#####\n#o  #\n# p #\n#.  #\n#####\n");
+        assert_eq!(stage.solve(), None);
     }
 }
@@ -0,0 +1,79 @@
+use crate::config::Font;
+use crate::{Object, Stage};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+
+// one color per `Object` variant, in the same order as the enum so `as usize`
+// indexes into it directly, same as `Font::glyphs`.
+const COLORS: [Color; 7] = [
+    Color::Reset,
+    Color::DarkGray,
+    Color::Yellow,
+    Color::Cyan,
+    Color::Green,
+    Color::White,
+    Color::Magenta,
+];
+
+pub fn render(frame: &mut Frame, stage: &Stage, font: &Font) {
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(stage.width as u16 + 2),
+            Constraint::Length(24),
+        ])
+        .split(frame.area());
+
+    render_board(frame, stage, font, columns[0]);
+    render_side_panel(frame, stage, columns[1]);
+}
+
+fn render_board(frame: &mut Frame, stage: &Stage, font: &Font, area: Rect) {
+    let glyphs = font.glyphs();
+    let block = Block::default()
+        .title(stage.name.as_str())
+        .borders(Borders::ALL);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let lines: Vec<Line> = (0..stage.height)
+        .map(|y| {
+            let cells: Vec<Span> = (0..stage.width)
+                .map(|x| {
+                    let object = stage.objects[y * stage.width + x];
+                    Span::styled(
+                        glyphs[object as usize].to_string(),
+                        Style::default().fg(COLORS[object as usize]),
+                    )
+                })
+                .collect();
+            Line::from(cells)
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn render_side_panel(frame: &mut Frame, stage: &Stage, area: Rect) {
+    let remaining = stage
+        .objects
+        .iter()
+        .filter(|object| matches!(object, Object::ObjBlock))
+        .count();
+
+    let mut lines = vec![
+        Line::from(format!("Level: {}", stage.name)),
+        Line::from(format!("Moves: {}", stage.move_count)),
+        Line::from(format!("Blocks left: {}", remaining)),
+    ];
+    if remaining == 0 {
+        lines.push(Line::from(""));
+        lines.push(Line::from("STAGE CLEAR!"));
+    }
+
+    let block = Block::default().title("Status").borders(Borders::ALL);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}